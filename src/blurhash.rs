@@ -0,0 +1,153 @@
+//!
+//! A compact BlurHash encoder: downscales an RGBA frame into a small set of 2D DCT-style basis
+//! components, quantizes them, and base83-encodes the result into the standard short string used
+//! to show a blurred placeholder while a full image or animation loads.
+//!
+//! See https://github.com/woltapp/blurhash for the format this implements.
+//!
+
+use image::RgbaImage;
+
+const BASE83_ALPHABET: &[u8; 83] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+// Encodes `image` into a BlurHash string using `components_x` by `components_y` basis
+// components (the typical default is 4x3).
+pub fn encode(image: &RgbaImage, components_x: u32, components_y: u32) -> String {
+    let factors = dct_factors(image, components_x, components_y);
+
+    let dc = factors[0];
+    let ac = &factors[1..];
+
+    let mut hash = String::new();
+    hash.push_str(&base83_encode(size_flag(components_x, components_y), 1));
+
+    let max_ac_component = ac.iter().flat_map(|&(r, g, b)| [r.abs(), g.abs(), b.abs()]).fold(0.0_f32, f32::max);
+    let quantized_max_ac = if ac.is_empty() {
+        0
+    } else {
+        ((max_ac_component * 166.0 - 0.5).clamp(0.0, 82.0)) as u32
+    };
+    hash.push_str(&base83_encode(quantized_max_ac, 1));
+
+    let actual_max_ac = if quantized_max_ac == 0 { 1.0 } else { (quantized_max_ac as f32 + 1.0) / 166.0 };
+
+    hash.push_str(&base83_encode(encode_dc(dc), 4));
+    for &(r, g, b) in ac {
+        hash.push_str(&base83_encode(encode_ac(r, g, b, actual_max_ac), 2));
+    }
+
+    hash
+}
+
+fn size_flag(components_x: u32, components_y: u32) -> u32 {
+    (components_x - 1) + (components_y - 1) * 9
+}
+
+// Computes the DCT-style basis components for `image` in linear RGB: component `[0]` is the
+// flat average color (the "DC" term) and the rest are the "AC" detail terms.
+fn dct_factors(image: &RgbaImage, components_x: u32, components_y: u32) -> Vec<(f32, f32, f32)> {
+    let (width, height) = image.dimensions();
+    let mut factors = Vec::with_capacity((components_x * components_y) as usize);
+
+    for cy in 0..components_y {
+        for cx in 0..components_x {
+            let (mut r, mut g, mut b) = (0.0_f32, 0.0_f32, 0.0_f32);
+
+            for y in 0..height {
+                for x in 0..width {
+                    let basis = ((std::f32::consts::PI * cx as f32 * x as f32) / width as f32).cos()
+                        * ((std::f32::consts::PI * cy as f32 * y as f32) / height as f32).cos();
+                    let pixel = image.get_pixel(x, y);
+                    r += basis * srgb_to_linear(pixel[0]);
+                    g += basis * srgb_to_linear(pixel[1]);
+                    b += basis * srgb_to_linear(pixel[2]);
+                }
+            }
+
+            let scale = if cx == 0 && cy == 0 { 1.0 } else { 2.0 };
+            let normalize = scale / (width * height) as f32;
+            factors.push((r * normalize, g * normalize, b * normalize));
+        }
+    }
+
+    factors
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u32 {
+    let v = value.clamp(0.0, 1.0);
+    let encoded = if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+    (encoded * 255.0).round().clamp(0.0, 255.0) as u32
+}
+
+// Packs the DC term's average color into a 24-bit (r, g, b) value.
+fn encode_dc((r, g, b): (f32, f32, f32)) -> u32 {
+    (linear_to_srgb(r) << 16) | (linear_to_srgb(g) << 8) | linear_to_srgb(b)
+}
+
+// Quantizes one AC term's (r, g, b) magnitude, relative to `max_ac`, into a single base-19 value
+// per channel, packed into one number the same way the DC term packs its 8-bit channels.
+fn encode_ac(r: f32, g: f32, b: f32, max_ac: f32) -> u32 {
+    let quantize = |value: f32| -> u32 {
+        let normalized = (value / max_ac).clamp(-1.0, 1.0);
+        (normalized.signum() * normalized.abs().powf(0.5) * 9.0 + 9.5).clamp(0.0, 18.0) as u32
+    };
+    (quantize(r) * 19 + quantize(g)) * 19 + quantize(b)
+}
+
+fn base83_encode(mut value: u32, length: usize) -> String {
+    let mut digits = vec![0u8; length];
+    for i in (0..length).rev() {
+        digits[i] = BASE83_ALPHABET[(value % 83) as usize];
+        value /= 83;
+    }
+    String::from_utf8(digits).expect("base83 alphabet is ASCII")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base83_encode_pads_to_the_requested_length() {
+        assert_eq!(base83_encode(0, 4), "0000");
+        assert_eq!(base83_encode(1, 1), "1");
+    }
+
+    #[test]
+    fn base83_encode_matches_known_values() {
+        // 83^1 encodes as "10" in two digits (first digit rolls over from 0 to 1).
+        assert_eq!(base83_encode(83, 2), "10");
+    }
+
+    #[test]
+    fn dct_factors_dc_term_is_the_flat_average_color() {
+        let mut image = RgbaImage::new(2, 2);
+        for pixel in image.pixels_mut() {
+            *pixel = image::Rgba([255, 0, 0, 255]);
+        }
+
+        let factors = dct_factors(&image, 2, 2);
+        let (r, g, b) = factors[0];
+
+        assert!((r - srgb_to_linear(255)).abs() < 1.0e-4);
+        assert!(g.abs() < 1.0e-6);
+        assert!(b.abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn dct_factors_returns_one_entry_per_component() {
+        let image = RgbaImage::new(4, 4);
+        let factors = dct_factors(&image, 4, 3);
+        assert_eq!(factors.len(), 12);
+    }
+}
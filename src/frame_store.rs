@@ -0,0 +1,157 @@
+//!
+//! A bounded-memory store for captured frames: every pushed frame is written straight through to
+//! an uncompressed scratch file on disk, while only the most recently pushed frames are kept
+//! around in RAM. This lets a session with thousands of frames avoid exhausting memory, and lets
+//! the scratch file be read back sequentially (even more than once) to re-encode or loop the
+//! captured animation into multiple output formats without re-running capture.
+//!
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use image::RgbaImage;
+
+use crate::Exit;
+
+// How many of the most recently pushed frames are kept decoded in memory.
+const RING_CAPACITY: usize = 32;
+
+pub struct FrameStore {
+    scratch_path: PathBuf,
+    scratch: File,
+    width: u32,
+    height: u32,
+    frame_bytes: usize,
+    delays: Vec<u16>,
+    ring: VecDeque<(usize, RgbaImage)>,
+}
+
+impl FrameStore {
+    pub fn new(scratch_path: PathBuf, width: u32, height: u32) -> FrameStore {
+        let scratch = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&scratch_path)
+            .or_exit("Could not create frame scratch file");
+        FrameStore {
+            scratch_path,
+            scratch,
+            width,
+            height,
+            frame_bytes: (width * height * 4) as usize,
+            delays: Vec::new(),
+            ring: VecDeque::with_capacity(RING_CAPACITY),
+        }
+    }
+
+    // Appends a captured frame to the scratch file and, space permitting, keeps it in the RAM
+    // ring so a recent re-read doesn't have to hit disk.
+    pub fn push(&mut self, frame: RgbaImage, delay: u16) {
+        self.scratch.write_all(frame.as_raw()).or_exit("Could not write frame to scratch file");
+
+        let index = self.delays.len();
+        self.delays.push(delay);
+
+        self.ring.push_back((index, frame));
+        if self.ring.len() > RING_CAPACITY {
+            self.ring.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.delays.len()
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    // Reads frame `index` back, from the RAM ring if it's still resident, otherwise by seeking
+    // into the scratch file.
+    pub fn get(&mut self, index: usize) -> (RgbaImage, u16) {
+        let delay = self.delays[index];
+
+        if let Some((_, frame)) = self.ring.iter().find(|(i, _)| *i == index) {
+            return (frame.clone(), delay);
+        }
+
+        let mut bytes = vec![0u8; self.frame_bytes];
+        self.scratch
+            .seek(SeekFrom::Start((index * self.frame_bytes) as u64))
+            .or_exit("Could not seek in frame scratch file");
+        self.scratch.read_exact(&mut bytes).or_exit("Could not read frame from scratch file");
+
+        let frame = RgbaImage::from_raw(self.width, self.height, bytes)
+            .or_exit("Corrupt frame in scratch file");
+        (frame, delay)
+    }
+
+    // Sequentially replays every captured frame, in order, handing each `(frame, delay)` pair to
+    // `visit`. Safe to call more than once on the same store, e.g. to re-encode into a second
+    // output format.
+    pub fn for_each<F: FnMut(RgbaImage, u16)>(&mut self, mut visit: F) {
+        for index in 0..self.len() {
+            let (frame, delay) = self.get(index);
+            visit(frame, delay);
+        }
+    }
+}
+
+impl Drop for FrameStore {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.scratch_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rusttygif-frame-store-test-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn get_round_trips_a_pushed_frame() {
+        let mut store = FrameStore::new(scratch_path("round-trip"), 2, 2);
+        let frame = RgbaImage::from_raw(2, 2, vec![1, 2, 3, 255, 4, 5, 6, 255, 7, 8, 9, 255, 10, 11, 12, 255])
+            .expect("test frame has the right byte length");
+
+        store.push(frame.clone(), 42);
+
+        let (got_frame, got_delay) = store.get(0);
+        assert_eq!(got_frame, frame);
+        assert_eq!(got_delay, 42);
+    }
+
+    #[test]
+    fn get_reads_frames_evicted_from_the_ram_ring() {
+        let mut store = FrameStore::new(scratch_path("eviction"), 1, 1);
+        let first = RgbaImage::from_raw(1, 1, vec![9, 8, 7, 255]).expect("test frame has the right byte length");
+        store.push(first.clone(), 1);
+
+        for i in 0..RING_CAPACITY {
+            let frame = RgbaImage::from_raw(1, 1, vec![0, 0, 0, 255]).expect("test frame has the right byte length");
+            store.push(frame, i as u16);
+        }
+
+        let (got_frame, got_delay) = store.get(0);
+        assert_eq!(got_frame, first);
+        assert_eq!(got_delay, 1);
+    }
+
+    #[test]
+    fn len_and_dimensions_reflect_pushed_frames() {
+        let mut store = FrameStore::new(scratch_path("len-and-dimensions"), 3, 2);
+        assert_eq!(store.len(), 0);
+        assert_eq!(store.dimensions(), (3, 2));
+
+        let frame = RgbaImage::new(3, 2);
+        store.push(frame, 5);
+        assert_eq!(store.len(), 1);
+    }
+}
@@ -1,27 +1,48 @@
-
-///
-/// A simple utility for creating animated GIF images from typescrips of terminal sessions.
-///
+//!
+//! A simple utility for creating animated GIF images from typescrips of terminal sessions.
+//!
 
 use std::fs::File;
 use std::io;
 use std::io::BufRead;
 use std::io::Read;
 use std::io::Write;
+use std::path::PathBuf;
 use std::process;
 use std::str;
 use std::str::FromStr;
+use std::sync::mpsc;
+use std::thread;
 use std::thread::sleep;
 use std::time::Duration;
 
+mod blurhash;
+mod frame_store;
+mod pptx;
+mod render;
+mod terminal;
+use frame_store::FrameStore;
+use render::RenderTarget;
+use terminal::HeadlessSession;
+
+// Default terminal dimensions used to size the headless emulator's grid when `$COLUMNS`/`$LINES`
+// aren't set in the environment.
+const DEFAULT_COLUMNS: usize = 80;
+const DEFAULT_ROWS: usize = 24;
+
+// How many rendered frames may queue up for the encoder thread before the replay-timed loop
+// blocks on `send`. Bounding this provides backpressure instead of letting memory grow unbounded
+// if encoding falls behind.
+const ENCODER_CHANNEL_CAPACITY: usize = 8;
+
 
 // Conveniences for exiting gracefully when encountering an error result.
-fn exit(message: &str) -> ! {
+pub(crate) fn exit(message: &str) -> ! {
     println!("{}", message);
     process::exit(1);
 }
 
-trait Exit<T, Error> {
+pub(crate) trait Exit<T, Error> {
     fn or_exit(self, message: &str) -> T;
 }
 
@@ -36,9 +57,18 @@ impl<T, Error: ::std::fmt::Display> Exit<T, Error> for Result<T, Error> {
     }
 }
 
+impl<T> Exit<T, &'static str> for Option<T> {
+    fn or_exit(self, message: &str) -> T {
+        match self {
+            Some(value) => value,
+            None => exit(message),
+        }
+    }
+}
+
 
 // Convenience method for executing a system command.
-fn execute<S: AsRef<std::ffi::OsStr> + std::fmt::Display>(args: &[S]) {
+pub(crate) fn execute<S: AsRef<std::ffi::OsStr> + std::fmt::Display>(args: &[S]) {
     process::Command::new(&args[0])
         .args(&args[1..])
         .stdout(std::process::Stdio::null())
@@ -50,93 +80,193 @@ fn execute<S: AsRef<std::ffi::OsStr> + std::fmt::Display>(args: &[S]) {
 // A silly convenience method for opening a file and returning a reader.
 fn reader<P: AsRef<std::path::Path>>(path: P) -> Result<std::io::BufReader<std::fs::File>, std::io::Error> {
     File::open(path)
-        .and_then(|file| Ok(io::BufReader::new(file)))
+        .map(io::BufReader::new)
+}
+
+// Reads a terminal dimension from the environment, falling back to `default` if unset or
+// unparseable.
+fn env_dimension(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| usize::from_str(&value).ok())
+        .unwrap_or(default)
 }
 
+// Converts a floating-point seconds delay (as found in the timing file) into a GIF frame delay,
+// which is expressed in hundredths of a second.
+fn delay_to_centisecs(delay: f64) -> u16 {
+    (delay * 100.0).round() as u16
+}
+
+// Spawns the background thread that owns `session` for the rest of the capture: it receives raw
+// typescript bytes (and the delay to associate with the resulting frame) over the returned
+// channel, feeds them into `session`, rasterizes the frame, and pushes it into a `FrameStore`
+// backed by `scratch_path`. Rendering is the expensive step (clearing and drawing glyphs for
+// every cell via `rusttype`), so keeping it here, off the replay-timed loop in `main`, is what
+// lets slow encoding fall behind without stretching the replay's `sleep` timing; the channel's
+// bounded capacity still provides backpressure if the consumer falls far enough behind.
+fn spawn_capture_thread(
+    mut session: HeadlessSession,
+    scratch_path: PathBuf,
+    width: u32,
+    height: u32,
+) -> (mpsc::SyncSender<(Vec<u8>, u16)>, thread::JoinHandle<FrameStore>) {
+    let (frame_tx, frame_rx) = mpsc::sync_channel::<(Vec<u8>, u16)>(ENCODER_CHANNEL_CAPACITY);
+    let store_thread = thread::spawn(move || {
+        let mut store = FrameStore::new(scratch_path, width, height);
+        for (bytes, delay) in frame_rx {
+            session.feed(&bytes);
+            store.push(session.render(), delay);
+        }
+        store
+    });
+    (frame_tx, store_thread)
+}
 
 fn main() {
 
     // Check dependencies.
     execute(&["script", "-V"]);
-    execute(&["xwd", "-help"]);
-    execute(&["convert", "-version"]);
 
-    // Check arguments.
+    // Check arguments. `--format` is optional and defaults to GIF; any remaining two arguments
+    // are taken to be the timing file and typescript, in that order.
     let args: Vec<String> = std::env::args().collect();
-    if args.len() != 3 {
-        exit("rusttygif <timingfile> <typescript>");
+    let mut format = RenderTarget::Gif;
+    let mut positional: Vec<&String> = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--format" {
+            let value = iter.next().or_exit("--format requires a value");
+            format = RenderTarget::from_str(value).or_exit("Invalid --format value");
+        } else {
+            positional.push(arg);
+        }
+    }
+    if positional.len() != 2 {
+        exit("rusttygif [--format {gif,sixel,kitty,mp4,pptx}] <timingfile> <typescript>");
     }
+    let (timing_path, script_path) = (positional[0], positional[1]);
 
     // Create output directory.
     let output_path = "output";
-    execute(&["mkdir", "-p", &output_path]);
+    execute(&["mkdir", "-p", output_path]);
 
     // Open input files.
-    let timing = reader(&args[1]).or_exit("Could not open timing file");
-    let mut script = reader(&args[2]).or_exit("Could not open script file");
+    let timing = reader(timing_path).or_exit("Could not open timing file");
+    let mut script = reader(script_path).or_exit("Could not open script file");
 
     // Ignore the first line containing the script's timestamp.
     let mut timestamp = String::new();
     script.read_line(&mut timestamp).or_exit("Could not read from script file");
 
-    // Read the timing and script files, line by line, replaying the script, taking screenshots,
-    // and building the command needed to assemble all screenshots into the animation as we go.
-    let mut frame: usize = 1;
+    // Read the timing and script files, line by line, replaying the script through a headless
+    // terminal emulator. The replay-timed loop below only feeds raw bytes into a channel and
+    // signals the delay to associate with the resulting frame; a separate thread owns the
+    // emulator session, rendering and pushing each frame into the frame store, so neither that
+    // rendering work nor a slow push ever stalls the `sleep`-driven replay timing.
     let mut buffer = vec![0u8; 0];
-    let mut convert: Vec<String> = Vec::new();
-    convert.push(String::from("convert"));
+    let session = HeadlessSession::new(
+        env_dimension("COLUMNS", DEFAULT_COLUMNS),
+        env_dimension("LINES", DEFAULT_ROWS),
+    );
+    let (width, height) = session.dimensions();
+
+    let scratch_path = PathBuf::from(format!("{}/frames.scratch", output_path));
+    let (frame_tx, store_thread) = spawn_capture_thread(session, scratch_path, width, height);
 
     for line in timing.lines() {
         let l = line.or_exit("Error reading line from timing file");
 
         // Line format is: <delay in seconds : float> <size in bytes : integer>
         let parts: Vec<&str> = l.split(" ").collect();
-        let delay = f64::from_str(&parts[0]).or_exit("Error reading delay in timing file");
-        let size = usize::from_str(&parts[1]).or_exit("Error reading size in timing file");
-
-        convert.push(String::from("-delay"));
-        convert.push(parts[0].to_string());
+        let delay = f64::from_str(parts[0]).or_exit("Error reading delay in timing file");
+        let size = usize::from_str(parts[1]).or_exit("Error reading size in timing file");
 
         // Decode the delay from a floating point into integer seconds and nanosecond parts.
         let delay_parts: Vec<&str> = parts[0].split(".").collect();
-        let delay_secs = u64::from_str(&delay_parts[0]).or_exit("Error parsing delay in timing file");
+        let delay_secs = u64::from_str(delay_parts[0]).or_exit("Error parsing delay in timing file");
         let delay_nsecs = (delay - delay_secs as f64) * 1.0e9;
         let duration = Duration::new(delay_secs, delay_nsecs as u32);
         sleep(duration);
 
         // The first time through, don't print anything.
         // XXX  Explain why this is correct.
-        if buffer.len() > 0 {
+        if !buffer.is_empty() {
             let slice = buffer.as_mut_slice();
             let output = str::from_utf8(slice).or_exit("Error converting script output to a string");
             print!("{}", output);
             io::stdout().flush().or_exit("Could not flush stdout");
 
-            let img_path = format!("{}/img-{}.xwd", output_path, frame);
-            let window = &std::env::var("WINDOWID").or_exit("Could not determine window ID");
-            execute(&["xwd", "-id", window, "-out", &img_path]);
-            convert.push(img_path);
+            frame_tx.send((slice.to_vec(), delay_to_centisecs(delay)))
+                .or_exit("Could not send frame to encoder thread");
         }
 
         // Explicitly set the buffer size to be exactly the number of bytes we want to read.
         // XXX  Figure out a cleaner way to do this.
         buffer.resize(size, 0);
-        let mut buffer_slice = buffer.as_mut_slice();
+        let buffer_slice = buffer.as_mut_slice();
         script.read_exact(buffer_slice).or_exit("Could not read from script file");
+    }
+
+    // All frames have been sent; let the store thread drain the channel and hand the finished
+    // store back before we encode it.
+    drop(frame_tx);
+    let mut store = match store_thread.join() {
+        Ok(store) => store,
+        Err(_) => exit("Encoder thread panicked while collecting frames"),
+    };
 
-        frame += 1;
+    // Assemble the captured frames using the selected output format.
+    render::encode(format, &mut store, output_path);
+
+    // Launch the default browser to view the image, if a GIF was produced. This is a nicety for
+    // interactive desktop use; `exo-open` is commonly unavailable on headless/CI boxes, so a
+    // failure to spawn it is not treated as fatal.
+    if format == RenderTarget::Gif {
+        let _ = process::Command::new("exo-open")
+            .args(["--launch", "WebBrowser", &format!("{}/output.gif", output_path)])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
     }
 
-    convert.push(String::from("-layers"));
-    convert.push(String::from("Optimize"));
-    convert.push(format!("{}/output.gif", output_path));
+    println!();
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Assemble and convert the animation.
-    execute(&convert);
+    #[test]
+    fn spawn_capture_thread_renders_fed_bytes_and_pushes_them_to_the_store() {
+        let session = HeadlessSession::new(4, 2);
+        let (width, height) = session.dimensions();
+        let scratch_path = std::env::temp_dir()
+            .join(format!("rusttygif-main-test-{}-capture-thread", std::process::id()));
+        let (frame_tx, store_thread) = spawn_capture_thread(session, scratch_path, width, height);
 
-    // Launch the default browser to view the image.
-    execute(&["exo-open", "--launch", "WebBrowser", &format!("{}/output.gif", output_path)]);
+        frame_tx.send((b"a".to_vec(), 5)).expect("channel has capacity for one frame");
+        drop(frame_tx);
+
+        let mut store = store_thread.join().expect("capture thread should not panic");
+        assert_eq!(store.len(), 1);
+        let (_, delay) = store.get(0);
+        assert_eq!(delay, 5);
+    }
 
-    println!("");
+    #[test]
+    fn encoder_channel_capacity_provides_backpressure() {
+        let (tx, _rx) = mpsc::sync_channel::<()>(ENCODER_CHANNEL_CAPACITY);
+        for _ in 0..ENCODER_CHANNEL_CAPACITY {
+            tx.try_send(()).expect("channel should accept up to its capacity");
+        }
+        assert!(matches!(tx.try_send(()), Err(mpsc::TrySendError::Full(()))));
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn store_thread_panic_is_reported_as_a_join_error() {
+        let handle: thread::JoinHandle<()> = thread::spawn(|| panic!("simulated encoder thread panic"));
+        assert!(handle.join().is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//!
+//! Writes the captured frames out as a PowerPoint (`.pptx`) deck: each frame becomes one slide,
+//! embedded as a PNG media part, with the per-frame timing delay driving that slide's automatic
+//! advance. A `.pptx` is an Office Open XML package, i.e. a ZIP archive of XML parts, so this
+//! builds that archive in memory and writes it straight to `path`.
+//!
+
+use std::io::Cursor;
+
+use image::codecs::png::PngEncoder;
+use image::{ImageEncoder, RgbaImage};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::frame_store::FrameStore;
+use crate::Exit;
+
+pub fn write(store: &mut FrameStore, path: &str) {
+    if crate::render::store_is_empty(store) {
+        crate::exit("No frames were captured; nothing to encode");
+    }
+
+    let (width, height) = store.dimensions();
+    let slide_count = store.len();
+
+    let file = std::fs::File::create(path).or_exit("Could not create output PPTX file");
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default();
+
+    add_part(&mut zip, &options, "[Content_Types].xml", &content_types_xml(slide_count));
+    add_part(&mut zip, &options, "_rels/.rels", ROOT_RELS_XML);
+    add_part(&mut zip, &options, "ppt/presentation.xml", &presentation_xml(slide_count));
+    add_part(&mut zip, &options, "ppt/_rels/presentation.xml.rels", &presentation_rels_xml(slide_count));
+    add_part(&mut zip, &options, "ppt/slideMasters/slideMaster1.xml", SLIDE_MASTER_XML);
+    add_part(&mut zip, &options, "ppt/slideMasters/_rels/slideMaster1.xml.rels", SLIDE_MASTER_RELS_XML);
+    add_part(&mut zip, &options, "ppt/slideLayouts/slideLayout1.xml", SLIDE_LAYOUT_XML);
+    add_part(&mut zip, &options, "ppt/theme/theme1.xml", THEME_XML);
+
+    let mut index = 0;
+    store.for_each(|frame, delay| {
+        index += 1;
+        add_slide(&mut zip, &options, index, width, height, &frame, delay);
+    });
+
+    zip.finish().or_exit("Could not finalize PPTX archive");
+}
+
+fn add_slide(
+    zip: &mut ZipWriter<std::fs::File>,
+    options: &FileOptions,
+    index: usize,
+    width: u32,
+    height: u32,
+    frame: &RgbaImage,
+    delay_centisecs: u16,
+) {
+    add_part(zip, options, &format!("ppt/slides/slide{}.xml", index), &slide_xml(delay_centisecs));
+    add_part(zip, options, &format!("ppt/slides/_rels/slide{}.xml.rels", index), &slide_rels_xml(index));
+
+    let mut png_bytes = Vec::new();
+    PngEncoder::new(&mut png_bytes)
+        .write_image(frame.as_raw(), width, height, image::ColorType::Rgba8)
+        .or_exit("Could not encode frame as PNG for PPTX media part");
+    add_binary_part(zip, options, &format!("ppt/media/image{}.png", index), &png_bytes);
+}
+
+fn add_part(zip: &mut ZipWriter<std::fs::File>, options: &FileOptions, name: &str, contents: &str) {
+    add_binary_part(zip, options, name, contents.as_bytes());
+}
+
+fn add_binary_part(zip: &mut ZipWriter<std::fs::File>, options: &FileOptions, name: &str, contents: &[u8]) {
+    zip.start_file(name, *options).or_exit(&format!("Could not start PPTX part \"{}\"", name));
+    std::io::copy(&mut Cursor::new(contents), zip).or_exit(&format!("Could not write PPTX part \"{}\"", name));
+}
+
+const ROOT_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="ppt/presentation.xml"/>
+</Relationships>"#;
+
+const SLIDE_MASTER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:sldMaster xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main">
+  <p:cSld><p:spTree/></p:cSld>
+  <p:sldLayoutIdLst><p:sldLayoutId id="2147483649" r:id="rId1" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"/></p:sldLayoutIdLst>
+</p:sldMaster>"#;
+
+const SLIDE_MASTER_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>
+  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" Target="../theme/theme1.xml"/>
+</Relationships>"#;
+
+const SLIDE_LAYOUT_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:sldLayout xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main" type="blank">
+  <p:cSld><p:spTree/></p:cSld>
+</p:sldLayout>"#;
+
+const THEME_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<a:theme xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" name="rusttygif">
+  <a:themeElements>
+    <a:clrScheme name="rusttygif">
+      <a:dk1><a:sysClr val="windowText" lastClr="000000"/></a:dk1>
+      <a:lt1><a:sysClr val="window" lastClr="FFFFFF"/></a:lt1>
+      <a:dk2><a:srgbClr val="000000"/></a:dk2>
+      <a:lt2><a:srgbClr val="FFFFFF"/></a:lt2>
+      <a:accent1><a:srgbClr val="4472C4"/></a:accent1>
+      <a:accent2><a:srgbClr val="ED7D31"/></a:accent2>
+      <a:accent3><a:srgbClr val="A5A5A5"/></a:accent3>
+      <a:accent4><a:srgbClr val="FFC000"/></a:accent4>
+      <a:accent5><a:srgbClr val="5B9BD5"/></a:accent5>
+      <a:accent6><a:srgbClr val="70AD47"/></a:accent6>
+      <a:hlink><a:srgbClr val="0563C1"/></a:hlink>
+      <a:folHlink><a:srgbClr val="954F72"/></a:folHlink>
+    </a:clrScheme>
+  </a:themeElements>
+</a:theme>"#;
+
+fn content_types_xml(slide_count: usize) -> String {
+    let mut overrides = String::new();
+    overrides.push_str(r#"<Override PartName="/ppt/presentation.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.presentation.main+xml"/>"#);
+    overrides.push_str(r#"<Override PartName="/ppt/slideMasters/slideMaster1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideMaster+xml"/>"#);
+    overrides.push_str(r#"<Override PartName="/ppt/slideLayouts/slideLayout1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideLayout+xml"/>"#);
+    overrides.push_str(r#"<Override PartName="/ppt/theme/theme1.xml" ContentType="application/vnd.openxmlformats-officedocument.theme+xml"/>"#);
+    for index in 1..=slide_count {
+        overrides.push_str(&format!(
+            r#"<Override PartName="/ppt/slides/slide{}.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slide+xml"/>"#,
+            index
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+  <Default Extension="png" ContentType="image/png"/>
+  {}
+</Types>"#,
+        overrides
+    )
+}
+
+fn presentation_xml(slide_count: usize) -> String {
+    let mut slide_ids = String::new();
+    for index in 1..=slide_count {
+        slide_ids.push_str(&format!(
+            r#"<p:sldId id="{}" r:id="rId{}"/>"#,
+            255 + index,
+            index + 1
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:presentation xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main">
+  <p:sldMasterIdLst><p:sldMasterId id="2147483648" r:id="rId1"/></p:sldMasterIdLst>
+  <p:sldIdLst>{}</p:sldIdLst>
+  <p:sldSz cx="9144000" cy="6858000"/>
+  <p:notesSz cx="6858000" cy="9144000"/>
+</p:presentation>"#,
+        slide_ids
+    )
+}
+
+fn presentation_rels_xml(slide_count: usize) -> String {
+    let mut relationships = String::new();
+    relationships.push_str(r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideMaster" Target="slideMasters/slideMaster1.xml"/>"#);
+    for index in 1..=slide_count {
+        relationships.push_str(&format!(
+            r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide" Target="slides/slide{}.xml"/>"#,
+            index + 1,
+            index
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+        relationships
+    )
+}
+
+fn slide_rels_xml(index: usize) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>
+  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="../media/image{}.png"/>
+</Relationships>"#,
+        index
+    )
+}
+
+// A slide with one full-bleed picture of the captured frame, advancing automatically after
+// `delay_centisecs` hundredths of a second.
+fn slide_xml(delay_centisecs: u16) -> String {
+    let advance_ms = delay_centisecs as u32 * 10;
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:sld xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main">
+  <p:cSld>
+    <p:spTree>
+      <p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr>
+      <p:grpSpPr/>
+      <p:pic>
+        <p:nvPicPr>
+          <p:cNvPr id="2" name="Frame"/>
+          <p:cNvPicPr/>
+          <p:nvPr/>
+        </p:nvPicPr>
+        <p:blipFill>
+          <a:blip r:embed="rId2"/>
+          <a:stretch><a:fillRect/></a:stretch>
+        </p:blipFill>
+        <p:spPr>
+          <a:xfrm><a:off x="0" y="0"/><a:ext cx="9144000" cy="6858000"/></a:xfrm>
+          <a:prstGeom prst="rect"><a:avLst/></a:prstGeom>
+        </p:spPr>
+      </p:pic>
+    </p:spTree>
+  </p:cSld>
+  <p:transition advTm="{}" advClick="0"/>
+</p:sld>"#,
+        advance_ms
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A minimal sanity check that a generated XML fragment's tags are balanced, catching e.g. a
+    // dropped closing tag or a stray `<`/`>` from a bad format string — not a full XML validator.
+    fn assert_balanced_tags(xml: &str) {
+        assert_eq!(xml.matches('<').count(), xml.matches('>').count(), "unbalanced angle brackets in: {}", xml);
+    }
+
+    #[test]
+    fn generated_xml_fragments_are_well_formed() {
+        assert_balanced_tags(&content_types_xml(3));
+        assert_balanced_tags(&presentation_xml(3));
+        assert_balanced_tags(&presentation_rels_xml(3));
+        assert_balanced_tags(&slide_xml(150));
+        assert_balanced_tags(&slide_rels_xml(2));
+    }
+
+    #[test]
+    fn content_types_xml_includes_one_override_per_slide() {
+        let xml = content_types_xml(3);
+        for index in 1..=3 {
+            assert!(xml.contains(&format!(r#"PartName="/ppt/slides/slide{}.xml""#, index)));
+        }
+        assert_eq!(xml.matches("presentationml.slide+xml").count(), 3);
+    }
+
+    #[test]
+    fn presentation_xml_numbers_slide_ids_and_rids_sequentially() {
+        let xml = presentation_xml(3);
+        assert!(xml.contains(r#"<p:sldId id="256" r:id="rId2"/>"#));
+        assert!(xml.contains(r#"<p:sldId id="257" r:id="rId3"/>"#));
+        assert!(xml.contains(r#"<p:sldId id="258" r:id="rId4"/>"#));
+    }
+
+    #[test]
+    fn presentation_rels_xml_numbers_slide_relationships_after_the_slide_master() {
+        let xml = presentation_rels_xml(3);
+        assert!(xml.contains(r#"Id="rId1""#) && xml.contains("slideMasters/slideMaster1.xml"));
+        assert!(xml.contains(r#"Id="rId2""#) && xml.contains(r#"Target="slides/slide1.xml""#));
+        assert!(xml.contains(r#"Id="rId3""#) && xml.contains(r#"Target="slides/slide2.xml""#));
+        assert!(xml.contains(r#"Id="rId4""#) && xml.contains(r#"Target="slides/slide3.xml""#));
+    }
+
+    #[test]
+    fn slide_rels_xml_embeds_the_matching_image_part() {
+        let xml = slide_rels_xml(5);
+        assert!(xml.contains(r#"Target="../media/image5.png""#));
+    }
+}
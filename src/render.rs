@@ -0,0 +1,365 @@
+//!
+//! Pluggable output encoders: the captured frames can be assembled into an animated GIF, streamed
+//! to the terminal as sixel or kitty inline images, or piped into `ffmpeg` to produce an MP4.
+//!
+
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::process;
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+
+use gif::{Encoder, Frame, Repeat};
+use image::RgbaImage;
+
+use crate::frame_store::FrameStore;
+use crate::{blurhash, pptx, Exit};
+
+// Default component counts used for the BlurHash written alongside the GIF.
+const BLURHASH_COMPONENTS_X: u32 = 4;
+const BLURHASH_COMPONENTS_Y: u32 = 3;
+
+// The output encoding selected via `--format`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderTarget {
+    Gif,
+    Sixel,
+    Kitty,
+    Mp4,
+    Pptx,
+}
+
+impl FromStr for RenderTarget {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<RenderTarget, String> {
+        match value {
+            "gif" => Ok(RenderTarget::Gif),
+            "sixel" => Ok(RenderTarget::Sixel),
+            "kitty" => Ok(RenderTarget::Kitty),
+            "mp4" => Ok(RenderTarget::Mp4),
+            "pptx" => Ok(RenderTarget::Pptx),
+            other => Err(format!("Unknown output format \"{}\" (expected gif, sixel, kitty, mp4, or pptx)", other)),
+        }
+    }
+}
+
+// Encodes the frames held in `store` using the selected target, writing into `output_path`.
+// `store` replays its frames sequentially from its scratch file, so this can be called more than
+// once (e.g. for multiple `--format`s) without re-running capture.
+pub fn encode(target: RenderTarget, store: &mut FrameStore, output_path: &str) {
+    match target {
+        RenderTarget::Gif => encode_gif(store, output_path),
+        RenderTarget::Sixel => stream_inline_images(store, encode_sixel),
+        RenderTarget::Kitty => stream_inline_images(store, encode_kitty),
+        RenderTarget::Mp4 => encode_mp4(store, &format!("{}/output.mp4", output_path)),
+        RenderTarget::Pptx => pptx::write(store, &format!("{}/output.pptx", output_path)),
+    }
+}
+
+// True if `store` holds no captured frames, i.e. there's nothing for an encoder to do.
+pub(crate) fn store_is_empty(store: &FrameStore) -> bool {
+    store.len() == 0
+}
+
+// Encodes every frame in `store`, in order, into an animated GIF written to `path`.
+fn encode_gif(store: &mut FrameStore, output_path: &str) {
+    if store_is_empty(store) {
+        crate::exit("No frames were captured; nothing to encode");
+    }
+
+    let (width, height) = store.dimensions();
+    let file = File::create(format!("{}/output.gif", output_path)).or_exit("Could not create output GIF file");
+    let mut encoder = Encoder::new(file, width as u16, height as u16, &[])
+        .or_exit("Could not initialize GIF encoder");
+    encoder.set_repeat(Repeat::Infinite).or_exit("Could not set GIF repeat mode");
+
+    store.for_each(|rgba, delay| {
+        let mut pixels = rgba.into_raw();
+        let mut gif_frame = Frame::from_rgba_speed(width as u16, height as u16, &mut pixels, 10);
+        gif_frame.delay = delay;
+        encoder.write_frame(&gif_frame).or_exit("Could not write GIF frame");
+    });
+
+    write_blurhash(store, output_path);
+}
+
+// Writes a BlurHash placeholder string, computed from the first captured frame, to
+// `output.blurhash` alongside the GIF — useful for showing a blurred preview while the full
+// animation loads.
+fn write_blurhash(store: &mut FrameStore, output_path: &str) {
+    let (first_frame, _) = store.get(0);
+    let hash = blurhash::encode(&first_frame, BLURHASH_COMPONENTS_X, BLURHASH_COMPONENTS_Y);
+    std::fs::write(format!("{}/output.blurhash", output_path), hash)
+        .or_exit("Could not write output.blurhash");
+}
+
+// Streams each frame in `store` to stdout as a terminal inline-image escape sequence, `sleep`ing
+// between frames for the delay preserved from the timing file. Shared by the sixel and kitty
+// targets, which only differ in how a single frame is encoded.
+fn stream_inline_images<F: Fn(&RgbaImage) -> String>(store: &mut FrameStore, encode_frame: F) {
+    store.for_each(|rgba, delay| {
+        print!("{}", encode_frame(&rgba));
+        io::stdout().flush().or_exit("Could not flush stdout");
+        sleep(Duration::from_millis(delay as u64 * 10));
+    });
+}
+
+// How many shades each of r/g/b is quantized to when building the sixel color palette. 6x6x6
+// (216 colors) comfortably fits under sixel's usual 256-register limit.
+const SIXEL_LEVELS: u32 = 6;
+
+// Encodes a single frame as a sixel image: pixels are quantized to a fixed 6x6x6 color cube, and
+// each 6-row band is emitted as one run of sixels per color in use, per the sixel graphics
+// protocol (DECGRA/DECGRI data strings inside a `\x1bP...\x1b\\` DCS).
+fn encode_sixel(rgba: &RgbaImage) -> String {
+    let (width, height) = rgba.dimensions();
+    let mut out = format!("\x1bPq\"1;1;{};{}", width, height);
+
+    for index in 0..SIXEL_LEVELS.pow(3) {
+        let (r, g, b) = sixel_palette_rgb(index);
+        out.push_str(&format!("#{};2;{};{};{}", index, r, g, b));
+    }
+
+    let mut row = 0;
+    while row < height {
+        let band_height = (height - row).min(6);
+
+        let mut used: Vec<u32> = Vec::new();
+        for x in 0..width {
+            for y in row..row + band_height {
+                let index = sixel_pixel_index(rgba, x, y);
+                if !used.contains(&index) {
+                    used.push(index);
+                }
+            }
+        }
+        used.sort_unstable();
+
+        for (i, &index) in used.iter().enumerate() {
+            if i > 0 {
+                out.push('$');
+            }
+            out.push_str(&format!("#{}", index));
+            for x in 0..width {
+                let mut bits = 0u8;
+                for dy in 0..band_height {
+                    if sixel_pixel_index(rgba, x, row + dy) == index {
+                        bits |= 1 << dy;
+                    }
+                }
+                out.push((63 + bits) as char);
+            }
+        }
+        out.push('-');
+        row += band_height;
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+fn sixel_pixel_index(rgba: &RgbaImage, x: u32, y: u32) -> u32 {
+    let quantize = |value: u8| -> u32 { (value as u32 * (SIXEL_LEVELS - 1) + 127) / 255 };
+    let pixel = rgba.get_pixel(x, y);
+    (quantize(pixel[0]) * SIXEL_LEVELS + quantize(pixel[1])) * SIXEL_LEVELS + quantize(pixel[2])
+}
+
+// Converts a palette index back into the sixel color register's r/g/b percentages (0-100).
+fn sixel_palette_rgb(index: u32) -> (u32, u32, u32) {
+    let b = index % SIXEL_LEVELS;
+    let g = (index / SIXEL_LEVELS) % SIXEL_LEVELS;
+    let r = index / (SIXEL_LEVELS * SIXEL_LEVELS);
+    let scale = |level: u32| level * 100 / (SIXEL_LEVELS - 1);
+    (scale(r), scale(g), scale(b))
+}
+
+// The largest base64 payload the kitty graphics protocol allows in a single `\x1b_G...\x1b\\` APC
+// sequence; longer payloads must be split across multiple sequences chained with `m=1`/`m=0`.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+// Encodes a single frame using the kitty graphics protocol: a base64 RGBA payload, chunked into
+// `\x1b_G...\x1b\\` APC sequences no larger than `KITTY_CHUNK_SIZE` bytes each, with `m=1` marking
+// all but the last chunk.
+fn encode_kitty(rgba: &RgbaImage) -> String {
+    let encoded = base64_encode(rgba.as_raw());
+    let mut chunks = encoded.as_bytes().chunks(KITTY_CHUNK_SIZE).peekable();
+
+    let mut out = String::new();
+    let first = chunks.next().unwrap_or(&[]);
+    out.push_str(&format!(
+        "\x1b_Ga=T,f=32,s={},v={},m={};{}\x1b\\",
+        rgba.width(),
+        rgba.height(),
+        chunks.peek().is_some() as u8,
+        std::str::from_utf8(first).expect("base64 alphabet is ASCII"),
+    ));
+
+    while let Some(chunk) = chunks.next() {
+        out.push_str(&format!(
+            "\x1b_Gm={};{}\x1b\\",
+            chunks.peek().is_some() as u8,
+            std::str::from_utf8(chunk).expect("base64 alphabet is ASCII"),
+        ));
+    }
+
+    out
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = *chunk.get(1).unwrap_or(&0);
+        let b2 = *chunk.get(2).unwrap_or(&0);
+
+        out.push(ALPHABET[(b0 >> 2) as usize] as char);
+        out.push(ALPHABET[(((b0 & 0x03) << 4) | (b1 >> 4)) as usize] as char);
+        out.push(if chunk.len() > 1 { ALPHABET[(((b1 & 0x0f) << 2) | (b2 >> 6)) as usize] as char } else { '=' });
+        out.push(if chunk.len() > 2 { ALPHABET[(b2 & 0x3f) as usize] as char } else { '=' });
+    }
+    out
+}
+
+// Pipes raw RGBA frame data into an `ffmpeg` child process, deriving `-framerate` from the
+// average delay between frames, to produce an MP4 written to `path`. Replays `store` twice: once
+// to total up the delays so `-framerate` can be passed before `ffmpeg` starts reading, and once
+// to stream the actual pixels.
+fn encode_mp4(store: &mut FrameStore, path: &str) {
+    if store_is_empty(store) {
+        crate::exit("No frames were captured; nothing to encode");
+    }
+
+    let (width, height) = store.dimensions();
+
+    let mut total_centisecs: f64 = 0.0;
+    store.for_each(|_, delay| total_centisecs += delay as f64);
+    let average_centisecs = total_centisecs / store.len() as f64;
+    let framerate = 100.0 / average_centisecs.max(1.0);
+
+    let mut child = process::Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-f", "rawvideo",
+            "-pix_fmt", "rgba",
+            "-video_size", &format!("{}x{}", width, height),
+            "-framerate", &format!("{:.3}", framerate),
+            "-i", "-",
+            "-pix_fmt", "yuv420p",
+            path,
+        ])
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .spawn()
+        .or_exit("Failed to execute process \"ffmpeg\"");
+
+    {
+        let stdin = child.stdin.as_mut().or_exit("Could not open ffmpeg's stdin");
+        store.for_each(|rgba, _| {
+            stdin.write_all(rgba.as_raw()).or_exit("Could not write frame to ffmpeg");
+        });
+    }
+
+    child.wait().or_exit("ffmpeg did not exit cleanly");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn scratch_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rusttygif-render-test-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn store_is_empty_reflects_whether_any_frame_was_pushed() {
+        let mut store = FrameStore::new(scratch_path("store-is-empty"), 1, 1);
+        assert!(store_is_empty(&store));
+
+        store.push(RgbaImage::new(1, 1), 1);
+        assert!(!store_is_empty(&store));
+    }
+
+    #[test]
+    fn encode_gif_writes_frame_delays_that_round_trip_through_the_encoder() {
+        let output_dir = std::env::temp_dir().join(format!("rusttygif-render-test-{}-encode-gif-output", std::process::id()));
+        std::fs::create_dir_all(&output_dir).expect("test output dir should be creatable");
+
+        let mut store = FrameStore::new(scratch_path("encode-gif-scratch"), 2, 2);
+        store.push(RgbaImage::new(2, 2), 12);
+        store.push(RgbaImage::new(2, 2), 34);
+
+        encode_gif(&mut store, output_dir.to_str().expect("test path should be valid UTF-8"));
+
+        let gif_bytes = std::fs::read(output_dir.join("output.gif")).expect("encode_gif should have written output.gif");
+        let mut decoder = gif::Decoder::new(gif_bytes.as_slice()).expect("written GIF should be decodable");
+        assert_eq!(decoder.width(), 2);
+        assert_eq!(decoder.height(), 2);
+
+        let first = decoder.read_next_frame().expect("should decode the first frame").expect("a first frame should exist");
+        assert_eq!(first.delay, 12);
+        let second = decoder.read_next_frame().expect("should decode the second frame").expect("a second frame should exist");
+        assert_eq!(second.delay, 34);
+
+        std::fs::remove_dir_all(&output_dir).ok();
+    }
+
+    #[test]
+    fn sixel_pixel_index_quantizes_pure_colors_to_palette_extremes() {
+        let mut image = RgbaImage::new(1, 1);
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+        assert_eq!(sixel_pixel_index(&image, 0, 0), 0);
+
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        assert_eq!(sixel_pixel_index(&image, 0, 0), (SIXEL_LEVELS - 1) * SIXEL_LEVELS * SIXEL_LEVELS);
+    }
+
+    #[test]
+    fn encode_sixel_emits_one_sixel_run_per_pixel_with_expected_bit_pattern() {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+        image.put_pixel(1, 0, Rgba([0, 0, 0, 255]));
+        let out = encode_sixel(&image);
+
+        assert!(out.starts_with("\x1bPq\"1;1;2;1"));
+        assert!(out.ends_with("\x1b\\"));
+        // Both pixels fall in palette index 0 with a one-row band, so the data run is two sixels
+        // of value (63 + 0b1) = '@'.
+        assert!(out.contains("#0@@-"));
+    }
+
+    #[test]
+    fn base64_encode_matches_known_values() {
+        assert_eq!(base64_encode(b"Ma"), "TWE=");
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+    }
+
+    #[test]
+    fn encode_kitty_chunks_reassemble_to_the_full_payload_with_correct_m_flags() {
+        // 32x32 RGBA is 4096 raw bytes, which base64-encodes past `KITTY_CHUNK_SIZE`, so this
+        // frame requires more than one chunk.
+        let image = RgbaImage::new(32, 32);
+        let out = encode_kitty(&image);
+
+        let chunks: Vec<&str> = out.split("\x1b\\").filter(|chunk| !chunk.is_empty()).collect();
+        assert!(chunks.len() > 1, "a 32x32 RGBA frame should need more than one kitty chunk");
+
+        let mut reassembled = String::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let body = chunk.strip_prefix("\x1b_G").expect("each chunk is a kitty APC sequence");
+            let (header, payload) = body.split_once(';').expect("chunk has a header;payload split");
+            reassembled.push_str(payload);
+
+            let is_last = i == chunks.len() - 1;
+            assert_eq!(header.contains("m=1"), !is_last);
+            assert_eq!(header.contains("m=0"), is_last);
+        }
+
+        assert_eq!(reassembled, base64_encode(image.as_raw()));
+    }
+}
@@ -0,0 +1,278 @@
+//!
+//! A minimal headless terminal emulator: feeds typescript bytes through a VT/ANSI parser,
+//! maintains a grid of styled cells, and rasterizes the grid to an image using a bundled
+//! monospace font. This lets `rusttygif` produce GIFs without an on-screen X11 window.
+//!
+
+use image::{Rgba, RgbaImage};
+use rusttype::{point, Font, Scale};
+use vte::{Params, Parser, Perform};
+
+use crate::Exit;
+
+const DEFAULT_FG: Rgba<u8> = Rgba([220, 220, 220, 255]);
+const DEFAULT_BG: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+const CELL_WIDTH: u32 = 10;
+const CELL_HEIGHT: u32 = 20;
+
+const FONT_BYTES: &[u8] = include_bytes!("../../assets/DejaVuSansMono.ttf");
+
+#[derive(Clone, Copy)]
+struct Cell {
+    ch: char,
+    fg: Rgba<u8>,
+    bg: Rgba<u8>,
+}
+
+impl Default for Cell {
+    fn default() -> Cell {
+        Cell { ch: ' ', fg: DEFAULT_FG, bg: DEFAULT_BG }
+    }
+}
+
+// A fixed-size grid of styled cells, tracking the emulator's cursor position.
+struct Grid {
+    columns: usize,
+    rows: usize,
+    cells: Vec<Cell>,
+    cursor_col: usize,
+    cursor_row: usize,
+}
+
+impl Grid {
+    fn new(columns: usize, rows: usize) -> Grid {
+        Grid {
+            columns,
+            rows,
+            cells: vec![Cell::default(); columns * rows],
+            cursor_col: 0,
+            cursor_row: 0,
+        }
+    }
+
+    fn cell_mut(&mut self, col: usize, row: usize) -> &mut Cell {
+        &mut self.cells[row * self.columns + col]
+    }
+
+    fn newline(&mut self) {
+        self.cursor_col = 0;
+        if self.cursor_row + 1 < self.rows {
+            self.cursor_row += 1;
+        } else {
+            self.scroll_up();
+        }
+    }
+
+    fn scroll_up(&mut self) {
+        self.cells.drain(0..self.columns);
+        self.cells.resize(self.columns * self.rows, Cell::default());
+    }
+
+    fn put(&mut self, ch: char, fg: Rgba<u8>, bg: Rgba<u8>) {
+        if self.cursor_col >= self.columns {
+            self.newline();
+        }
+        let (col, row) = (self.cursor_col, self.cursor_row);
+        *self.cell_mut(col, row) = Cell { ch, fg, bg };
+        self.cursor_col += 1;
+    }
+}
+
+// Implements the VT/ANSI escape handling needed to drive the grid: printable characters,
+// carriage returns/linefeeds, and SGR (`m`) sequences for foreground/background color.
+struct Emulator {
+    grid: Grid,
+    fg: Rgba<u8>,
+    bg: Rgba<u8>,
+}
+
+impl Emulator {
+    fn new(columns: usize, rows: usize) -> Emulator {
+        Emulator { grid: Grid::new(columns, rows), fg: DEFAULT_FG, bg: DEFAULT_BG }
+    }
+
+    fn apply_sgr(&mut self, params: &Params) {
+        for param in params.iter() {
+            match param[0] {
+                0 => {
+                    self.fg = DEFAULT_FG;
+                    self.bg = DEFAULT_BG;
+                }
+                30..=37 => self.fg = ansi_color(param[0] - 30),
+                40..=47 => self.bg = ansi_color(param[0] - 40),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Perform for Emulator {
+    fn print(&mut self, c: char) {
+        self.grid.put(c, self.fg, self.bg);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' => self.grid.newline(),
+            b'\r' => self.grid.cursor_col = 0,
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, _intermediates: &[u8], _ignore: bool, action: char) {
+        if action == 'm' {
+            self.apply_sgr(params);
+        }
+    }
+}
+
+fn ansi_color(index: u16) -> Rgba<u8> {
+    const PALETTE: [[u8; 3]; 8] = [
+        [0, 0, 0],
+        [205, 0, 0],
+        [0, 205, 0],
+        [205, 205, 0],
+        [0, 0, 238],
+        [205, 0, 205],
+        [0, 205, 205],
+        [229, 229, 229],
+    ];
+    let [r, g, b] = PALETTE[index as usize];
+    Rgba([r, g, b, 255])
+}
+
+// Replays typescript bytes through a headless terminal emulator and rasterizes the resulting
+// grid to a sequence of frames, one per timing-file entry.
+pub struct HeadlessSession {
+    emulator: Emulator,
+    parser: Parser,
+    font: Font<'static>,
+}
+
+impl HeadlessSession {
+    pub fn new(columns: usize, rows: usize) -> HeadlessSession {
+        let font = Font::try_from_bytes(FONT_BYTES).or_exit("Could not parse bundled monospace font");
+        HeadlessSession { emulator: Emulator::new(columns, rows), parser: Parser::new(), font }
+    }
+
+    // Feeds `bytes` (one timing-file entry's worth of typescript output) into the emulator. The
+    // parser is kept across calls so an escape sequence split across two timing-file entries
+    // still parses correctly.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.parser.advance(&mut self.emulator, *byte);
+        }
+    }
+
+    // The pixel dimensions a rendered frame will have, derived from the grid's columns/rows.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.emulator.grid.columns as u32 * CELL_WIDTH, self.emulator.grid.rows as u32 * CELL_HEIGHT)
+    }
+
+    // Rasterizes the current grid state into an RGBA frame.
+    pub fn render(&self) -> RgbaImage {
+        let grid = &self.emulator.grid;
+        let width = grid.columns as u32 * CELL_WIDTH;
+        let height = grid.rows as u32 * CELL_HEIGHT;
+        let mut image = RgbaImage::new(width, height);
+
+        for row in 0..grid.rows {
+            for col in 0..grid.columns {
+                let cell = grid.cells[row * grid.columns + col];
+                fill_cell(&mut image, col, row, cell.bg);
+                if cell.ch != ' ' {
+                    draw_glyph(&mut image, &self.font, col, row, cell.ch, cell.fg);
+                }
+            }
+        }
+
+        image
+    }
+}
+
+fn fill_cell(image: &mut RgbaImage, col: usize, row: usize, color: Rgba<u8>) {
+    let (x0, y0) = (col as u32 * CELL_WIDTH, row as u32 * CELL_HEIGHT);
+    for y in y0..y0 + CELL_HEIGHT {
+        for x in x0..x0 + CELL_WIDTH {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
+
+fn draw_glyph(image: &mut RgbaImage, font: &Font, col: usize, row: usize, ch: char, color: Rgba<u8>) {
+    let scale = Scale::uniform(CELL_HEIGHT as f32 * 0.8);
+    let origin = point(col as f32 * CELL_WIDTH as f32, row as f32 * CELL_HEIGHT as f32 + CELL_HEIGHT as f32 * 0.8);
+    let glyph = font.glyph(ch).scaled(scale).positioned(origin);
+
+    if let Some(bounds) = glyph.pixel_bounding_box() {
+        glyph.draw(|gx, gy, coverage| {
+            let (x, y) = (bounds.min.x + gx as i32, bounds.min.y + gy as i32);
+            if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+                let blended = Rgba([color[0], color[1], color[2], (coverage * 255.0) as u8]);
+                image.put_pixel(x as u32, y as u32, blended);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_put_advances_cursor() {
+        let mut grid = Grid::new(3, 2);
+        grid.put('a', DEFAULT_FG, DEFAULT_BG);
+        assert_eq!(grid.cursor_col, 1);
+        assert_eq!(grid.cell_mut(0, 0).ch, 'a');
+    }
+
+    #[test]
+    fn grid_put_wraps_to_next_row_at_column_edge() {
+        let mut grid = Grid::new(2, 2);
+        grid.put('a', DEFAULT_FG, DEFAULT_BG);
+        grid.put('b', DEFAULT_FG, DEFAULT_BG);
+        grid.put('c', DEFAULT_FG, DEFAULT_BG);
+        assert_eq!(grid.cursor_row, 1);
+        assert_eq!(grid.cursor_col, 1);
+        assert_eq!(grid.cell_mut(0, 1).ch, 'c');
+    }
+
+    #[test]
+    fn grid_scroll_up_drops_top_row() {
+        let mut grid = Grid::new(2, 2);
+        grid.put('a', DEFAULT_FG, DEFAULT_BG);
+        grid.newline();
+        grid.put('b', DEFAULT_FG, DEFAULT_BG);
+        grid.newline();
+        assert_eq!(grid.cell_mut(0, 0).ch, 'b');
+        assert_eq!(grid.cell_mut(0, 1).ch, ' ');
+    }
+
+    #[test]
+    fn emulator_execute_handles_newline_and_carriage_return() {
+        let mut emulator = Emulator::new(4, 2);
+        emulator.print('a');
+        emulator.execute(b'\n');
+        assert_eq!(emulator.grid.cursor_row, 1);
+        emulator.execute(b'\r');
+        assert_eq!(emulator.grid.cursor_col, 0);
+    }
+
+    #[test]
+    fn emulator_apply_sgr_sets_and_resets_colors() {
+        let mut emulator = Emulator::new(4, 2);
+        let mut parser = Parser::new();
+        for byte in b"\x1b[31m" {
+            parser.advance(&mut emulator, *byte);
+        }
+        assert_eq!(emulator.fg, ansi_color(1));
+
+        for byte in b"\x1b[0m" {
+            parser.advance(&mut emulator, *byte);
+        }
+        assert_eq!(emulator.fg, DEFAULT_FG);
+        assert_eq!(emulator.bg, DEFAULT_BG);
+    }
+}